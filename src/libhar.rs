@@ -2,8 +2,10 @@
 
 use serde_json;
 use std::cmp;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use url::percent_encoding::percent_decode;
 use url::Url;
@@ -87,23 +89,157 @@ pub struct Doc {
     pub log: Log,
 }
 
+/// Wrap `f` in a decompressing reader if `path`'s extension says it is compressed
+/// (`.gz`, `.zst`, `.bz2`); otherwise pass the file through unchanged.
+fn decompressing_reader(path: &Path, f: File) -> Result<Box<Read>, Box<Error>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Ok(Box::new(flate2::read::GzDecoder::new(f))),
+        Some("zst") => Ok(Box::new(zstd::Decoder::new(f)?)),
+        Some("bz2") => Ok(Box::new(bzip2::read::BzDecoder::new(f))),
+        _ => Ok(Box::new(f)),
+    }
+}
+
 pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Doc, Box<Error>> {
-    let f = File::open(path)?;
-    let doc: Doc = serde_json::from_reader(f)?;
+    let f = File::open(path.as_ref())?;
+    let reader = decompressing_reader(path.as_ref(), f)?;
+    let doc: Doc = serde_json::from_reader(reader)?;
     Ok(doc)
 }
 
+/// A `Read` adapter over the body of a top-level JSON array (positioned just after
+/// its opening `[`): it skips whitespace and the commas between elements so each
+/// element can be deserialized on its own, and reports EOF at the matching `]`.
+///
+/// Brace/bracket depth is only tracked outside of JSON string literals: a string
+/// value may legitimately contain `{`, `}`, `,` etc. (and `\"` doesn't end it), so
+/// those bytes must pass through untouched instead of perturbing `depth`.
+struct ArrayElementReader<R: Read> {
+    inner: R,
+    depth: i32,
+    in_string: bool,
+    escape: bool,
+    done: bool,
+}
+
+impl<R: Read> Read for ArrayElementReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let mut byte = [0u8; 1];
+        loop {
+            if self.inner.read(&mut byte)? == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+            let b = byte[0];
+
+            if self.in_string {
+                buf[0] = b;
+                if self.escape {
+                    self.escape = false;
+                } else if b == b'\\' {
+                    self.escape = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                }
+                return Ok(1);
+            }
+
+            match b {
+                b'"' => {
+                    self.in_string = true;
+                    buf[0] = b;
+                    return Ok(1);
+                }
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    buf[0] = b;
+                    return Ok(1);
+                }
+                b'}' | b']' => {
+                    if self.depth == 0 {
+                        self.done = true;
+                        return Ok(0);
+                    }
+                    self.depth -= 1;
+                    buf[0] = b;
+                    return Ok(1);
+                }
+                b',' | b' ' | b'\n' | b'\r' | b'\t' if self.depth == 0 => continue,
+                _ => {
+                    buf[0] = b;
+                    return Ok(1);
+                }
+            }
+        }
+    }
+}
+
+/// Advance `r` past the `"entries":[` marker so the remainder of the stream is the
+/// body of the entries array. This is a pragmatic scan, not a general JSON parser:
+/// it assumes the HAR was produced with the usual field layout.
+fn skip_to_entries_array<R: BufRead>(mut r: R) -> Result<R, Box<Error>> {
+    let needle = b"\"entries\"";
+    let mut window: Vec<u8> = Vec::with_capacity(needle.len());
+    let mut byte = [0u8; 1];
+    loop {
+        if r.read(&mut byte)? == 0 {
+            return Err(From::from("Could not find an \"entries\" field in the HAR stream"));
+        }
+        window.push(byte[0]);
+        if window.len() > needle.len() {
+            window.remove(0);
+        }
+        if window.as_slice() == needle {
+            break;
+        }
+    }
+    loop {
+        if r.read(&mut byte)? == 0 {
+            return Err(From::from(
+                "Unexpected end of HAR stream while looking for the entries array",
+            ));
+        }
+        if byte[0] == b'[' {
+            break;
+        }
+    }
+    Ok(r)
+}
+
+/// Iterate `doc.log.entries` one at a time without holding the whole `Doc` (or even
+/// the whole entries array) in memory, so huge or compressed HARs can still be
+/// scanned by `entries`, `summary` and `search`.
+pub fn read_entries_streaming<P: AsRef<Path>>(
+    path: P,
+) -> Result<impl Iterator<Item = Result<Entry, serde_json::Error>>, Box<Error>> {
+    let f = File::open(path.as_ref())?;
+    let reader = decompressing_reader(path.as_ref(), f)?;
+    let reader = skip_to_entries_array(BufReader::new(reader))?;
+    let reader = ArrayElementReader {
+        inner: reader,
+        depth: 0,
+        in_string: false,
+        escape: false,
+        done: false,
+    };
+    Ok(serde_json::Deserializer::from_reader(reader).into_iter::<Entry>())
+}
+
+fn is_excluded(name: &str, excludes: Option<&Vec<&str>>) -> bool {
+    match excludes {
+        Some(excl) => excl.contains(&name),
+        None => false,
+    }
+}
+
 fn print_name_vals(nvs: &Vec<NameValue>, excludes: Option<&Vec<&str>>) {
     let mut sorted: Vec<&NameValue> = nvs.iter().collect();
     sorted.sort_by(|a, b| a.name.cmp(&b.name));
     for nv in sorted.iter() {
-        let mut show = true;
-        if let Some(excl) = excludes {
-            if excl.contains(&nv.name.as_str()) {
-                show = false;
-            }
-        }
-        if show {
+        if !is_excluded(&nv.name, excludes) {
             println!("        {:20} {}", format!("{}{}", nv.name, ":"), nv.value);
         }
     }
@@ -119,39 +255,140 @@ fn cut_text(text: &str, maxlen: usize) -> String {
         .to_string()
 }
 
-fn expand_privates_2(pr: serde_json::Value) -> Result<serde_json::Value, Box<Error>> {
-    if let Some(s) = pr.as_str() {
-        let decoded_s = percent_decode(s.as_bytes()).decode_utf8()?;
-        return Ok(serde_json::from_str(&decoded_s)?);
-    };
-    Ok(pr)
+/// One rule describing where a `privateData`-style blob may live: either an
+/// exact JSON-pointer path, or a field-name pattern (`*` wildcard) matched
+/// against every key in the tree.
+pub struct PrivateDataRule {
+    pointer: Option<String>,
+    field_name: Option<String>,
 }
 
-fn expand_privates(text: &str) -> Result<serde_json::Value, Box<Error>> {
-    let mut doc: serde_json::Value = serde_json::from_str(text)?;
-    // TODO Scan for arbitrary positions of private data, not only in 'AddDevice.DevicePrivateData'.
-    // TODO Instead of cloning, use pointer_mut()
-    let pr = doc["AddDevice"]["DevicePrivateData"].clone();
-    if pr != serde_json::Value::Null {
-        doc["AddDevice"]["DevicePrivateData"] = expand_privates_2(pr)?;
-        return Ok(doc);
+fn parse_private_data_rule(spec: &str) -> PrivateDataRule {
+    if spec.starts_with('/') {
+        PrivateDataRule {
+            pointer: Some(spec.to_string()),
+            field_name: None,
+        }
+    } else {
+        PrivateDataRule {
+            pointer: None,
+            field_name: Some(spec.to_string()),
+        }
+    }
+}
+
+fn default_private_data_rules(profile: &str) -> Vec<PrivateDataRule> {
+    match profile {
+        "ecs" => vec![
+            parse_private_data_rule("/AddDevice/DevicePrivateData"),
+            parse_private_data_rule("/Resource/Device/DevicePrivateData"),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Load the `privateData` expansion rules for `profile` from
+/// `private_data_profiles.<profile>` in `conf` (a list of JSON-pointer paths
+/// and/or `*`-wildcard field-name patterns), falling back to the built-in
+/// preset for `profile` when it isn't configured. Only `"ecs"` has a built-in
+/// preset; any other unconfigured profile yields no rules.
+pub fn load_private_data_rules(conf: &config::Config, profile: &str) -> Vec<PrivateDataRule> {
+    match conf.get::<Vec<String>>(&format!("private_data_profiles.{}", profile)) {
+        Ok(specs) => specs.iter().map(|s| parse_private_data_rule(s)).collect(),
+        Err(_) => default_private_data_rules(profile),
+    }
+}
+
+// Minimal single-'*' glob matcher, e.g. "*PrivateData" against "DevicePrivateData".
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == text,
+        Some(ix) => {
+            let prefix = &pattern[..ix];
+            let suffix = &pattern[ix + 1..];
+            text.len() >= prefix.len() + suffix.len()
+                && text.starts_with(prefix)
+                && text.ends_with(suffix)
+        }
+    }
+}
+
+// If `slot` is a string, percent-decode it and re-parse it as embedded JSON.
+// `walk_private_data` recurses into the result afterwards, so nested encoded
+// blobs (string or already-structured) expand fully too.
+fn expand_slot(slot: &mut serde_json::Value) -> Result<(), Box<Error>> {
+    if let Some(s) = slot.as_str().map(str::to_string) {
+        let decoded = percent_decode(s.as_bytes()).decode_utf8()?;
+        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&decoded) {
+            *slot = parsed;
+        }
     }
-    let pr = doc["Resource"]["Device"]["DevicePrivateData"].clone();
-    if pr != serde_json::Value::Null {
-        doc["Resource"]["Device"]["DevicePrivateData"] = expand_privates_2(pr)?;
-        return Ok(doc);
+    Ok(())
+}
+
+fn walk_private_data(v: &mut serde_json::Value, field_globs: &[&str]) -> Result<(), Box<Error>> {
+    match v {
+        serde_json::Value::Object(map) => {
+            let keys: Vec<String> = map.keys().cloned().collect();
+            for key in keys {
+                let is_target = field_globs.iter().any(|g| glob_match(g, &key));
+                if let Some(child) = map.get_mut(&key) {
+                    // Expand a matching string value, then always recurse into
+                    // whatever is there now (a just-expanded blob, or an already
+                    // structured value) so nested private-data fields aren't missed.
+                    if is_target {
+                        expand_slot(child)?;
+                    }
+                    walk_private_data(child, field_globs)?;
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items.iter_mut() {
+                walk_private_data(item, field_globs)?;
+            }
+        }
+        _ => {}
     }
-    return Ok(doc);
+    Ok(())
 }
 
-pub fn print_body(doc: &Doc, num: usize, which: &str, ecs: bool) {
+fn apply_private_data_rules(
+    doc: &mut serde_json::Value,
+    rules: &[PrivateDataRule],
+) -> Result<(), Box<Error>> {
+    let field_globs: Vec<&str> = rules
+        .iter()
+        .filter_map(|r| r.field_name.as_ref().map(|s| s.as_str()))
+        .collect();
+
+    for rule in rules {
+        if let Some(ref pointer) = rule.pointer {
+            if let Some(slot) = doc.pointer_mut(pointer) {
+                expand_slot(slot)?;
+            }
+        }
+    }
+    if !field_globs.is_empty() {
+        walk_private_data(doc, &field_globs)?;
+    }
+    Ok(())
+}
+
+fn expand_privates(text: &str, rules: &[PrivateDataRule]) -> Result<serde_json::Value, Box<Error>> {
+    let mut doc: serde_json::Value = serde_json::from_str(text)?;
+    apply_private_data_rules(&mut doc, rules)?;
+    Ok(doc)
+}
+
+pub fn print_body(doc: &Doc, num: usize, which: &str, rules: Option<&[PrivateDataRule]>) {
     let e = &doc.log.entries[num];
     match which {
         "req" => {
             match e.request.postData {
                 Some(ref data) => {
-                    if ecs {
-                        let text = expand_privates(&data.text).unwrap();
+                    if let Some(rules) = rules {
+                        let text = expand_privates(&data.text, rules).unwrap();
                         println!("{}", text);
                     } else {
                         println!("{}", data.text);
@@ -163,8 +400,8 @@ pub fn print_body(doc: &Doc, num: usize, which: &str, ecs: bool) {
             }
         }
         "resp" => {
-            if ecs {
-                let text = expand_privates(&e.response.content.text).unwrap();
+            if let Some(rules) = rules {
+                let text = expand_privates(&e.response.content.text, rules).unwrap();
                 println!("{}", text);
             } else {
                 println!("{}", e.response.content.text);
@@ -174,54 +411,787 @@ pub fn print_body(doc: &Doc, num: usize, which: &str, ecs: bool) {
     }
 }
 
+fn filtered_name_vals(nvs: &Vec<NameValue>, excludes: Option<&Vec<&str>>) -> Vec<NameValue> {
+    nvs.iter()
+        .filter(|nv| !is_excluded(&nv.name, excludes))
+        .map(|nv| NameValue {
+            name: nv.name.clone(),
+            value: nv.value.clone(),
+        })
+        .collect()
+}
+
+/// Compact, machine-readable analysis of a single entry, as produced by
+/// `summary --json`. Unset/empty fields are omitted rather than serialized as
+/// `null`/`[]`.
+#[derive(Serialize, Debug)]
+pub struct EntryAnalysis {
+    pub index: usize,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_mime_type: Option<String>,
+    pub response_mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body_size: Option<usize>,
+    pub response_body_size: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub request_headers: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub response_headers: Vec<NameValue>,
+}
+
+fn analyze_entry(
+    ix: usize,
+    e: &Entry,
+    short_url: bool,
+    query_string_excludes: Option<&Vec<&str>>,
+    headers_excludes: Option<&Vec<&str>>,
+) -> Result<EntryAnalysis, Box<Error>> {
+    let req = &e.request;
+    let mut url = Url::parse(&req.url)?;
+    if short_url {
+        url.set_query(None);
+    }
+
+    Ok(EntryAnalysis {
+        index: ix,
+        method: req.method.clone(),
+        url: url.into_string(),
+        status: e.response.status,
+        request_mime_type: req.postData.as_ref().map(|pd| pd.mimeType.clone()),
+        response_mime_type: e.response.content.mimeType.clone(),
+        request_body_size: req.postData.as_ref().map(|pd| pd.text.len()),
+        response_body_size: e.response.content.size,
+        query_string: filtered_name_vals(&req.queryString, query_string_excludes),
+        request_headers: filtered_name_vals(&req.headers, headers_excludes),
+        response_headers: filtered_name_vals(&e.response.headers, headers_excludes),
+    })
+}
+
+fn print_entry_json(
+    ix: usize,
+    e: &Entry,
+    short_url: bool,
+    query_string_excludes: Option<&Vec<&str>>,
+    headers_excludes: Option<&Vec<&str>>,
+) -> Result<(), Box<Error>> {
+    let analysis = analyze_entry(ix, e, short_url, query_string_excludes, headers_excludes)?;
+    println!("{}", serde_json::to_string(&analysis)?);
+    Ok(())
+}
+
+fn print_entry_text(
+    ix: usize,
+    e: &Entry,
+    short_url: bool,
+    query_string_excludes: Option<&Vec<&str>>,
+    headers_excludes: Option<&Vec<&str>>,
+) -> Result<(), Box<Error>> {
+    let req = &e.request;
+    let mut url = Url::parse(&req.url)?;
+    if short_url {
+        url.set_query(None);
+    }
+    println!("{}/ {} {}", ix, req.method, url);
+    if req.queryString.len() > 0 {
+        println!("    Query String:");
+        print_name_vals(&req.queryString, query_string_excludes);
+    }
+    if req.headers.len() > 0 {
+        println!("    Headers:");
+        print_name_vals(&req.headers, headers_excludes);
+    }
+    if let Some(ref pd) = req.postData {
+        println!("    Post Data:");
+        println!("        Mime-Type:           {}", pd.mimeType);
+        println!("        Length:              {}", pd.text.len());
+        println!("        Text:                {}…", cut_text(&pd.text, 80));
+    }
+
+    let resp = &e.response;
+    println!(
+        "{}/ RESPONSE:                 {} {}",
+        ix, resp.status, resp.statusText
+    );
+    if resp.headers.len() > 0 {
+        println!("    Headers:");
+        print_name_vals(&resp.headers, headers_excludes);
+    }
+    println!("    Content:");
+    println!("        Mime-Type:           {}", resp.content.mimeType);
+    println!("        Size:                {}", resp.content.size);
+    println!(
+        "        Text:                {}…",
+        cut_text(&resp.content.text, 80)
+    );
+
+    println!("\n\n");
+
+    Ok(())
+}
+
 pub fn print_overview(
     doc: &Doc,
     short_url: bool,
     query_string_excludes: Option<&Vec<&str>>,
     headers_excludes: Option<&Vec<&str>>,
+    json: bool,
 ) -> Result<(), Box<Error>> {
+    if json {
+        for (ix, e) in doc.log.entries.iter().enumerate() {
+            print_entry_json(ix, e, short_url, query_string_excludes, headers_excludes)?;
+        }
+        return Ok(());
+    }
+
     println!("{:?} entries", doc.log.entries.len());
     for (ix, e) in doc.log.entries.iter().enumerate() {
-        let req = &e.request;
-        let mut url = Url::parse(&req.url)?;
-        if short_url {
-            url.set_query(None);
+        print_entry_text(ix, e, short_url, query_string_excludes, headers_excludes)?;
+    }
+
+    Ok(())
+}
+
+/// Same as `print_overview`, but consumes entries lazily from `read_entries_streaming`
+/// instead of a fully-loaded `Doc`, so `summary` can run on huge HARs. Since the total
+/// count isn't known up front, the text mode skips the leading "N entries" line that
+/// `print_overview` prints.
+pub fn print_overview_streaming<I>(
+    entries: I,
+    short_url: bool,
+    query_string_excludes: Option<&Vec<&str>>,
+    headers_excludes: Option<&Vec<&str>>,
+    json: bool,
+) -> Result<(), Box<Error>>
+where
+    I: Iterator<Item = Result<Entry, serde_json::Error>>,
+{
+    for (ix, entry) in entries.enumerate() {
+        let e = entry?;
+        if json {
+            print_entry_json(ix, &e, short_url, query_string_excludes, headers_excludes)?;
+        } else {
+            print_entry_text(ix, &e, short_url, query_string_excludes, headers_excludes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Which parts of an entry `search` should look at.
+///
+/// `req`/`resp` select the message side, `headers`/`body`/`url` select the field
+/// kind. The two dimensions are independent: giving a value in only one of them
+/// leaves the other dimension unrestricted, e.g. `--in resp,body` searches only
+/// response bodies, but `--in body` alone searches bodies on both sides. See
+/// `SearchScope::parse`.
+pub struct SearchScope {
+    pub req: bool,
+    pub resp: bool,
+    pub headers: bool,
+    pub body: bool,
+    pub url: bool,
+    pub query: bool,
+}
+
+impl SearchScope {
+    pub fn all() -> SearchScope {
+        SearchScope {
+            req: true,
+            resp: true,
+            headers: true,
+            body: true,
+            url: true,
+            query: true,
         }
-        println!("{}/ {} {}", ix, req.method, url);
-        if req.queryString.len() > 0 {
-            println!("    Query String:");
-            print_name_vals(&req.queryString, query_string_excludes);
+    }
+
+    /// Parse a comma-separated `--in` value. An absent or empty spec means "search
+    /// everywhere". `req`/`resp` (message side) and `headers`/`body`/`url`/`query`
+    /// (field kind) are independent dimensions: naming a value in one dimension
+    /// restricts only that dimension, leaving the other dimension at its "search
+    /// everywhere" default. So `--in headers` alone still searches headers on both
+    /// sides (and does not search query strings, since they aren't headers), and
+    /// `--in resp` alone still searches every field kind on the response side.
+    pub fn parse(spec: Option<&str>) -> SearchScope {
+        let spec = match spec {
+            Some(s) if !s.is_empty() => s,
+            _ => return SearchScope::all(),
+        };
+        let mut scope = SearchScope {
+            req: false,
+            resp: false,
+            headers: false,
+            body: false,
+            url: false,
+            query: false,
+        };
+        let mut side_given = false;
+        let mut kind_given = false;
+        for part in spec.split(',') {
+            match part.trim() {
+                "req" => {
+                    scope.req = true;
+                    side_given = true;
+                }
+                "resp" => {
+                    scope.resp = true;
+                    side_given = true;
+                }
+                "headers" => {
+                    scope.headers = true;
+                    kind_given = true;
+                }
+                "body" => {
+                    scope.body = true;
+                    kind_given = true;
+                }
+                "url" => {
+                    scope.url = true;
+                    kind_given = true;
+                }
+                "query" => {
+                    scope.query = true;
+                    kind_given = true;
+                }
+                _ => {}
+            }
         }
-        if req.headers.len() > 0 {
-            println!("    Headers:");
-            print_name_vals(&req.headers, headers_excludes);
+        if !side_given {
+            scope.req = true;
+            scope.resp = true;
         }
-        if let Some(ref pd) = req.postData {
-            println!("    Post Data:");
-            println!("        Mime-Type:           {}", pd.mimeType);
-            println!("        Length:              {}", pd.text.len());
-            println!("        Text:                {}…", cut_text(&pd.text, 80));
+        if !kind_given {
+            scope.headers = true;
+            scope.body = true;
+            scope.url = true;
+            scope.query = true;
         }
+        scope
+    }
+}
 
-        let resp = &e.response;
-        println!(
-            "{}/ RESPONSE:                 {} {}",
-            ix, resp.status, resp.statusText
-        );
-        if resp.headers.len() > 0 {
-            println!("    Headers:");
-            print_name_vals(&resp.headers, headers_excludes);
+pub struct SearchOptions<'a> {
+    pub ignore_case: bool,
+    pub regex: bool,
+    pub scope: SearchScope,
+    pub headers_excludes: Option<&'a Vec<&'a str>>,
+}
+
+enum Matcher {
+    Plain { needle: String, ignore_case: bool },
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    fn new(pattern: &str, ignore_case: bool, use_regex: bool) -> Result<Matcher, Box<Error>> {
+        if use_regex {
+            let re = regex::RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()?;
+            Ok(Matcher::Regex(re))
+        } else {
+            Ok(Matcher::Plain {
+                needle: pattern.to_string(),
+                ignore_case,
+            })
+        }
+    }
+
+    fn find(&self, text: &str) -> Option<String> {
+        match *self {
+            Matcher::Regex(ref re) => re.find(text).map(|m| m.as_str().to_string()),
+            Matcher::Plain {
+                ref needle,
+                ignore_case,
+            } => {
+                if ignore_case {
+                    find_ignore_case(text, needle)
+                } else {
+                    let start = text.find(needle.as_str())?;
+                    Some(text[start..start + needle.len()].to_string())
+                }
+            }
+        }
+    }
+}
+
+// Case-folding (`to_lowercase`) can change a string's byte length (e.g. Turkish
+// "İ" -> "i̇"), so an offset found in a lowercased copy of `text` is not safe to
+// slice into the original `text` with. Instead, only ever slice `text` at its own
+// char boundaries (from `char_indices`/`len`) and compare candidates lowercased.
+fn find_ignore_case(text: &str, needle: &str) -> Option<String> {
+    let needle_lower = needle.to_lowercase();
+    if needle_lower.is_empty() {
+        return Some(String::new());
+    }
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).chain(Some(text.len())).collect();
+    for (bi, &start) in boundaries.iter().enumerate() {
+        if start == text.len() {
+            break;
+        }
+        for &end in &boundaries[bi + 1..] {
+            let candidate = &text[start..end];
+            let candidate_lower = candidate.to_lowercase();
+            if candidate_lower.len() < needle_lower.len() {
+                continue;
+            }
+            if candidate_lower == needle_lower {
+                return Some(candidate.to_string());
+            }
+            break;
+        }
+    }
+    None
+}
+
+/// One match found by `search`: which entry, which field (e.g. `resp.header:Content-Type`,
+/// `req.body`), and the matched text.
+#[derive(Serialize, Debug)]
+pub struct SearchHit {
+    pub entry: usize,
+    pub field: String,
+    pub value: String,
+}
+
+fn search_entry(ix: usize, e: &Entry, matcher: &Matcher, opts: &SearchOptions, hits: &mut Vec<SearchHit>) {
+    if opts.scope.req {
+        if opts.scope.url {
+            if let Some(value) = matcher.find(&e.request.url) {
+                hits.push(SearchHit {
+                    entry: ix,
+                    field: "req.url".to_string(),
+                    value,
+                });
+            }
+        }
+        if opts.scope.headers {
+            for nv in &e.request.headers {
+                if is_excluded(&nv.name, opts.headers_excludes) {
+                    continue;
+                }
+                if let Some(value) = matcher.find(&nv.value) {
+                    hits.push(SearchHit {
+                        entry: ix,
+                        field: format!("req.header:{}", nv.name),
+                        value,
+                    });
+                }
+            }
+        }
+        if opts.scope.query {
+            for nv in &e.request.queryString {
+                if let Some(value) = matcher.find(&nv.value) {
+                    hits.push(SearchHit {
+                        entry: ix,
+                        field: format!("req.query:{}", nv.name),
+                        value,
+                    });
+                }
+            }
+        }
+        if opts.scope.body {
+            if let Some(ref pd) = e.request.postData {
+                if let Some(value) = matcher.find(&pd.text) {
+                    hits.push(SearchHit {
+                        entry: ix,
+                        field: "req.body".to_string(),
+                        value,
+                    });
+                }
+            }
+        }
+    }
+    if opts.scope.resp {
+        if opts.scope.headers {
+            for nv in &e.response.headers {
+                if is_excluded(&nv.name, opts.headers_excludes) {
+                    continue;
+                }
+                if let Some(value) = matcher.find(&nv.value) {
+                    hits.push(SearchHit {
+                        entry: ix,
+                        field: format!("resp.header:{}", nv.name),
+                        value,
+                    });
+                }
+            }
+        }
+        if opts.scope.body {
+            if let Some(value) = matcher.find(&e.response.content.text) {
+                hits.push(SearchHit {
+                    entry: ix,
+                    field: "resp.body".to_string(),
+                    value,
+                });
+            }
+        }
+    }
+}
+
+/// Search entries streamed from `read_entries_streaming`, so huge HARs don't need to
+/// be held in memory as a `Doc` just to be grepped.
+pub fn search<I>(entries: I, pattern: &str, opts: &SearchOptions) -> Result<Vec<SearchHit>, Box<Error>>
+where
+    I: Iterator<Item = Result<Entry, serde_json::Error>>,
+{
+    let matcher = Matcher::new(pattern, opts.ignore_case, opts.regex)?;
+    let mut hits = Vec::new();
+
+    for (ix, entry) in entries.enumerate() {
+        let e = entry?;
+        search_entry(ix, &e, &matcher, opts, &mut hits);
+    }
+
+    Ok(hits)
+}
+
+pub fn print_search_hits(hits: &[SearchHit], json: bool) {
+    for hit in hits {
+        if json {
+            println!("{}", serde_json::to_string(hit).unwrap());
+        } else {
+            println!("{}/ {}: {}", hit.entry, hit.field, hit.value);
+        }
+    }
+}
+
+/// Whether `print_graph` emits a directed `digraph` or an undirected `graph`.
+pub enum GraphKind {
+    Digraph,
+    Graph,
+}
+
+impl GraphKind {
+    fn keyword(&self) -> &'static str {
+        match *self {
+            GraphKind::Digraph => "digraph",
+            GraphKind::Graph => "graph",
         }
-        println!("    Content:");
-        println!("        Mime-Type:           {}", resp.content.mimeType);
-        println!("        Size:                {}", resp.content.size);
+    }
+
+    fn edge_op(&self) -> &'static str {
+        match *self {
+            GraphKind::Digraph => "->",
+            GraphKind::Graph => "--",
+        }
+    }
+}
+
+// DOT quoted strings only need '"' and '\' escaped.
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn status_color(status: u16) -> &'static str {
+    match status / 100 {
+        2 => "darkgreen",
+        3 => "blue",
+        4 => "orange",
+        5 => "red",
+        _ => "gray",
+    }
+}
+
+fn node_key(method: &str, url: &Url) -> String {
+    format!("{} {}", method, url.path())
+}
+
+/// Emit a Graphviz DOT representation of the call sequence: one node per distinct
+/// `METHOD path`, edges between consecutive entries, and dashed edges for redirects.
+pub fn print_graph(doc: &Doc, kind: GraphKind) -> Result<(), Box<Error>> {
+    let op = kind.edge_op();
+    println!("{} haricot {{", kind.keyword());
+
+    let mut node_ids: HashMap<String, usize> = HashMap::new();
+    // Redirects commonly switch HTTP method (e.g. POST -> 302 -> GET), so redirect
+    // edges are resolved by path alone via this secondary, method-agnostic index.
+    let mut path_to_id: HashMap<String, usize> = HashMap::new();
+    let mut node_labels: Vec<(String, u16)> = Vec::new();
+    let mut entry_nodes: Vec<usize> = Vec::with_capacity(doc.log.entries.len());
+
+    for e in &doc.log.entries {
+        let url = Url::parse(&e.request.url)?;
+        let key = node_key(&e.request.method, &url);
+        let id = match node_ids.get(&key) {
+            Some(&id) => id,
+            None => {
+                let id = node_labels.len();
+                node_labels.push((key.clone(), e.response.status));
+                node_ids.insert(key, id);
+                path_to_id.entry(url.path().to_string()).or_insert(id);
+                id
+            }
+        };
+        entry_nodes.push(id);
+    }
+
+    for (id, &(ref key, status)) in node_labels.iter().enumerate() {
         println!(
-            "        Text:                {}…",
-            cut_text(&resp.content.text, 80)
+            "    n{} [label=\"{} ({})\", color={}];",
+            id,
+            escape_label(key),
+            status,
+            status_color(status)
         );
+    }
+
+    for w in entry_nodes.windows(2) {
+        println!("    n{} {} n{};", w[0], op, w[1]);
+    }
+
+    for (ix, e) in doc.log.entries.iter().enumerate() {
+        if e.response.redirectURL.is_empty() {
+            continue;
+        }
+        let redirect_url = match Url::parse(&e.response.redirectURL) {
+            Ok(u) => u,
+            Err(_) => continue,
+        };
+        let target_path = redirect_url.path();
+
+        // Prefer the entry that immediately follows when its URL matches the
+        // redirect target -- that's the request actually being followed, even if
+        // it used a different method than the one that redirected.
+        let next_id = doc.log.entries.get(ix + 1).and_then(|next| {
+            Url::parse(&next.request.url)
+                .ok()
+                .filter(|u| u.path() == target_path)
+                .map(|_| entry_nodes[ix + 1])
+        });
+
+        if let Some(target_id) = next_id.or_else(|| path_to_id.get(target_path).cloned()) {
+            println!(
+                "    n{} {} n{} [style=dashed, label=\"redirect\"];",
+                entry_nodes[ix], op, target_id
+            );
+        }
+    }
+
+    println!("}}");
+
+    Ok(())
+}
+
+// Escape for a POSIX single-quoted shell string: close the quote, emit an
+// escaped quote, and reopen it.
+fn quote_single(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+// Percent-decoded query values may themselves contain '&'/'='/'#'/'+'/'%'/' '; re-encode
+// those so a value like "foo&bar" isn't misread as two pairs, and so a literal space
+// doesn't break the URL curl is given. Only these characters matter here — everything
+// else is left as the readable, decoded text `readable_url` is for.
+fn encode_query_delims(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('&', "%26")
+        .replace('=', "%3D")
+        .replace('#', "%23")
+        .replace('+', "%2B")
+        .replace(' ', "%20")
+}
+
+// Rebuild `raw`'s query string from percent-decoded pairs so the printed URL is
+// readable, while leaving the scheme/host/path exactly as captured.
+fn readable_url(raw: &str) -> String {
+    let decoded_query: Vec<String> = match Url::parse(raw) {
+        Ok(ref url) if url.query().is_some() => url
+            .query_pairs()
+            .map(|(k, v)| format!("{}={}", encode_query_delims(&k), encode_query_delims(&v)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let base = match raw.find('?') {
+        Some(ix) => &raw[..ix],
+        None => raw,
+    };
+    if decoded_query.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, decoded_query.join("&"))
+    }
+}
+
+fn sorted_headers(nvs: &Vec<NameValue>) -> Vec<&NameValue> {
+    let mut sorted: Vec<&NameValue> = nvs.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted
+}
 
-        println!("\n\n");
+/// Print a `curl` command line that replays an entry's request.
+pub fn print_curl(doc: &Doc, num: usize, headers_excludes: Option<&Vec<&str>>) {
+    let req = &doc.log.entries[num].request;
+    print!(
+        "curl -X {} {}",
+        req.method,
+        quote_single(&readable_url(&req.url))
+    );
+    for nv in sorted_headers(&req.headers) {
+        if is_excluded(&nv.name, headers_excludes) {
+            continue;
+        }
+        print!(
+            " \\\n  -H {}",
+            quote_single(&format!("{}: {}", nv.name, nv.value))
+        );
     }
+    if let Some(ref pd) = req.postData {
+        print!(" \\\n  --data {}", quote_single(&pd.text));
+    }
+    println!();
+}
 
+/// Print the raw HTTP/1.1 request line, headers and body that an entry's
+/// request was (or would be) sent as on the wire.
+pub fn print_http(doc: &Doc, num: usize, headers_excludes: Option<&Vec<&str>>) -> Result<(), Box<Error>> {
+    let req = &doc.log.entries[num].request;
+    let url = Url::parse(&req.url)?;
+    let mut request_target = url.path().to_string();
+    if let Some(q) = url.query() {
+        request_target.push('?');
+        request_target.push_str(q);
+    }
+    print!("{} {} HTTP/1.1\r\n", req.method, request_target);
+    for nv in sorted_headers(&req.headers) {
+        if is_excluded(&nv.name, headers_excludes) {
+            continue;
+        }
+        print!("{}: {}\r\n", nv.name, nv.value);
+    }
+    print!("\r\n");
+    if let Some(ref pd) = req.postData {
+        print!("{}", pd.text);
+    }
+    println!();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_ignore_case_matches_plain_ascii() {
+        assert_eq!(
+            find_ignore_case("Hello World", "world"),
+            Some("World".to_string())
+        );
+    }
+
+    #[test]
+    fn find_ignore_case_returns_none_when_absent() {
+        assert_eq!(find_ignore_case("Hello World", "xyz"), None);
+    }
+
+    #[test]
+    fn find_ignore_case_does_not_panic_on_length_changing_case_folding() {
+        // Turkish "İ" lowercases to the two-byte "i̇", so a naive byte offset taken
+        // from a lowercased copy would not line up with the original string.
+        let text = "İstanbul";
+        assert_eq!(find_ignore_case(text, "istanbul"), Some(text.to_string()));
+    }
+
+    #[test]
+    fn matcher_plain_case_sensitive() {
+        let m = Matcher::new("World", false, false).unwrap();
+        assert_eq!(m.find("Hello World"), Some("World".to_string()));
+        assert_eq!(m.find("Hello world"), None);
+    }
+
+    #[test]
+    fn matcher_plain_ignore_case() {
+        let m = Matcher::new("world", true, false).unwrap();
+        assert_eq!(m.find("Hello World"), Some("World".to_string()));
+    }
+
+    #[test]
+    fn matcher_regex() {
+        let m = Matcher::new(r"\d+", false, true).unwrap();
+        assert_eq!(m.find("order 1234"), Some("1234".to_string()));
+    }
+
+    #[test]
+    fn search_scope_in_headers_leaves_query_strings_out() {
+        let scope = SearchScope::parse(Some("headers"));
+        assert!(scope.headers);
+        assert!(!scope.query);
+        assert!(!scope.body);
+        assert!(!scope.url);
+        // Message side wasn't named, so it still defaults to "everywhere".
+        assert!(scope.req);
+        assert!(scope.resp);
+    }
+
+    #[test]
+    fn search_scope_in_resp_leaves_every_field_kind_on() {
+        let scope = SearchScope::parse(Some("resp"));
+        assert!(scope.resp);
+        assert!(!scope.req);
+        assert!(scope.headers);
+        assert!(scope.body);
+        assert!(scope.url);
+        assert!(scope.query);
+    }
+
+    #[test]
+    fn search_scope_default_is_everywhere() {
+        let scope = SearchScope::parse(None);
+        assert!(scope.req);
+        assert!(scope.resp);
+        assert!(scope.headers);
+        assert!(scope.body);
+        assert!(scope.url);
+        assert!(scope.query);
+    }
+
+    fn read_array_elements(json_array_body: &str) -> Vec<String> {
+        let reader = ArrayElementReader {
+            inner: std::io::Cursor::new(json_array_body.as_bytes()),
+            depth: 0,
+            in_string: false,
+            escape: false,
+            done: false,
+        };
+        let mut elements = Vec::new();
+        for value in serde_json::Deserializer::from_reader(reader).into_iter::<serde_json::Value>() {
+            elements.push(value.unwrap().to_string());
+        }
+        elements
+    }
+
+    #[test]
+    fn array_element_reader_splits_plain_elements() {
+        let elements = read_array_elements(r#"{"a":1},{"a":2}"#);
+        assert_eq!(elements, vec!["{\"a\":1}".to_string(), "{\"a\":2}".to_string()]);
+    }
+
+    #[test]
+    fn array_element_reader_ignores_braces_and_commas_inside_strings() {
+        // A naive brace/comma counter would split this on the comma or the braces
+        // that appear inside the string value, instead of treating it as one element.
+        let elements = read_array_elements(r#"{"body":"{a, b}, {c}"}"#);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0], r#"{"body":"{a, b}, {c}"}"#);
+    }
+
+    #[test]
+    fn array_element_reader_handles_escaped_quotes() {
+        let elements = read_array_elements(r#"{"body":"she said \"hi\", then left"}"#);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0], r#"{"body":"she said \"hi\", then left"}"#);
+    }
+
+    #[test]
+    fn skip_to_entries_array_finds_marker_and_stops_after_opening_bracket() {
+        let doc = br#"{"log":{"version":"1.2","entries":[{"a":1},{"a":2}]}}"#;
+        let mut reader = skip_to_entries_array(BufReader::new(std::io::Cursor::new(&doc[..]))).unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, r#"{"a":1},{"a":2}]}}"#);
+    }
+}
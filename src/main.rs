@@ -5,12 +5,16 @@ extern crate chrono;
 extern crate slog_term;
 #[macro_use]
 extern crate clap;
+extern crate bzip2;
 extern crate config;
+extern crate flate2;
+extern crate regex;
 extern crate serde;
 extern crate serde_json;
 extern crate url;
 #[macro_use]
 extern crate serde_derive;
+extern crate zstd;
 
 use clap::{App, Arg, ArgMatches, SubCommand};
 use slog::Drain;
@@ -56,32 +60,92 @@ fn run(
         "X-Requested-With",
     ];
 
-    let doc = libhar::read_file(fnhar)?;
-
+    // `entries`, `summary` and `search` only need to look at entries one at a time, so
+    // serve them from the streaming reader and avoid loading the whole (possibly
+    // multi-gigabyte) HAR into memory.
     match matches.subcommand() {
+        ("entries", Some(_)) => {
+            let mut count = 0usize;
+            for entry in libhar::read_entries_streaming(fnhar)? {
+                entry?;
+                count += 1;
+            }
+            println!("{}", count);
+            return Ok(());
+        }
         ("summary", Some(my_matches)) => {
             let short_url = !my_matches.is_present("with-query-string");
+            let json = my_matches.is_present("json");
+            let entries = libhar::read_entries_streaming(fnhar)?;
             if my_matches.is_present("ecs") {
-                libhar::print_overview(
-                    &doc,
+                libhar::print_overview_streaming(
+                    entries,
                     short_url,
                     Some(&query_string_excludes),
                     Some(&headers_excludes),
+                    json,
                 )?
             } else {
-                libhar::print_overview(&doc, short_url, None, None)?
+                libhar::print_overview_streaming(entries, short_url, None, None, json)?
             }
+            return Ok(());
         }
-        ("entries", Some(my_matches)) => {
-            println!("{}", doc.log.entries.len());
+        ("search", Some(my_matches)) => {
+            let pattern = my_matches.value_of("pattern").unwrap();
+            let opts = libhar::SearchOptions {
+                ignore_case: my_matches.is_present("ignore-case"),
+                regex: my_matches.is_present("regex"),
+                scope: libhar::SearchScope::parse(my_matches.value_of("in")),
+                headers_excludes: if my_matches.is_present("ecs") {
+                    Some(&headers_excludes)
+                } else {
+                    None
+                },
+            };
+            let entries = libhar::read_entries_streaming(fnhar)?;
+            let hits = libhar::search(entries, pattern, &opts)?;
+            libhar::print_search_hits(&hits, my_matches.is_present("json"));
+            return Ok(());
         }
+        _ => {}
+    }
+
+    let doc = libhar::read_file(fnhar)?;
+
+    match matches.subcommand() {
         ("body", Some(my_matches)) => {
             let num = value_t!(my_matches, "num", usize)?;
             let which = my_matches.value_of("which").unwrap();
-            let ecs = my_matches.is_present("ecs");
-            libhar::print_body(&doc, num, &which, ecs);
+            let rules = if my_matches.is_present("ecs") {
+                Some(libhar::load_private_data_rules(conf, "ecs"))
+            } else {
+                None
+            };
+            libhar::print_body(&doc, num, &which, rules.as_ref().map(|r| r.as_slice()));
         }
-        _ => libhar::print_overview(&doc, false, None, None)?,
+        ("graph", Some(my_matches)) => {
+            let kind = if my_matches.is_present("undirected") {
+                libhar::GraphKind::Graph
+            } else {
+                libhar::GraphKind::Digraph
+            };
+            libhar::print_graph(&doc, kind)?;
+        }
+        ("replay", Some(my_matches)) => {
+            let num = value_t!(my_matches, "num", usize)?;
+            let format = my_matches.value_of("format").unwrap();
+            let excludes = if my_matches.is_present("ecs") {
+                Some(&headers_excludes)
+            } else {
+                None
+            };
+            match format {
+                "curl" => libhar::print_curl(&doc, num, excludes),
+                "http" => libhar::print_http(&doc, num, excludes)?,
+                _ => unreachable!(),
+            }
+        }
+        _ => libhar::print_overview(&doc, false, None, None, false)?,
     }
 
     Ok(())
@@ -119,6 +183,10 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                          .long("with-query-string")
                          .help("By default we print the URL without its query string (we list the query pairs anyways). If this flag is set, we print the URL as-is, including query string (if it has one).")
                     )
+                    .arg(Arg::with_name("json")
+                         .long("json")
+                         .help("Emit one compact JSON analysis object per entry instead of human-formatted text")
+                    )
         )
         .subcommand(SubCommand::with_name("entries")
                     .about("Count entries")
@@ -127,7 +195,7 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                     .about("Get body data")
                     .arg(Arg::with_name("ecs")
                          .long("ecs")
-                         .help("Perform some transformations specific to ECS: (1) If body JSON contains field 'privateData', expand this into JSON as well.")
+                         .help("Expand encoded privateData fields using the 'ecs' profile (see 'private_data_profiles' in the config file)")
                     )
                     .arg(Arg::with_name("num")
                         .value_name("NUM")
@@ -141,6 +209,62 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                         .help("Get body of request 'req' or response 'resp'")
                     )
         )
+        .subcommand(SubCommand::with_name("search")
+                    .about("Search entries for a pattern in URLs, headers, query strings and bodies")
+                    .arg(Arg::with_name("ecs")
+                         .long("ecs")
+                         .help("Exclude some headers suitable for analysis of ECS")
+                    )
+                    .arg(Arg::with_name("ignore-case")
+                         .short("i")
+                         .long("ignore-case")
+                         .help("Match case-insensitively")
+                    )
+                    .arg(Arg::with_name("regex")
+                         .long("regex")
+                         .help("Treat PATTERN as a regular expression instead of a plain substring")
+                    )
+                    .arg(Arg::with_name("in")
+                         .long("in")
+                         .value_name("SCOPE")
+                         .takes_value(true)
+                         .help("Comma-separated list of where to search. Message side (req,resp) and field kind (headers,body,url,query) are independent: naming one doesn't restrict the other, e.g. 'headers' alone searches headers on both sides (query strings aren't headers, so they aren't searched). Default: everywhere.")
+                    )
+                    .arg(Arg::with_name("json")
+                         .long("json")
+                         .help("Emit each match as a JSON object {entry, field, value} instead of one line of text")
+                    )
+                    .arg(Arg::with_name("pattern")
+                        .value_name("PATTERN")
+                        .required(true)
+                        .help("Pattern to search for")
+                    )
+        )
+        .subcommand(SubCommand::with_name("graph")
+                    .about("Emit a Graphviz DOT graph of the request/response flow")
+                    .arg(Arg::with_name("undirected")
+                         .long("undirected")
+                         .help("Emit an undirected 'graph' instead of a 'digraph'")
+                    )
+        )
+        .subcommand(SubCommand::with_name("replay")
+                    .about("Reconstruct an executable request from an entry")
+                    .arg(Arg::with_name("ecs")
+                         .long("ecs")
+                         .help("Strip headers excluded for ECS analysis, e.g. Authorization, Cookie")
+                    )
+                    .arg(Arg::with_name("num")
+                        .value_name("NUM")
+                        .required(true)
+                        .help("Reconstruct the request of this entry")
+                    )
+                    .arg(Arg::with_name("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["curl", "http"])
+                        .required(true)
+                        .help("Emit as a 'curl' command line or as a raw 'http' wire message")
+                    )
+        )
         .get_matches()
 }
 